@@ -6,14 +6,15 @@
 use std::error::Error;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::{self, BufRead};
 use std::path::Path;
-use std::collections::{HashMap};
+use std::str::FromStr;
 
 use thiserror::Error;
 
 mod transaction;
 mod portfolio;
+mod price;
+mod output;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum LibError {
@@ -21,38 +22,42 @@ pub enum LibError {
   HistoryAccessError
 }
 
-fn read_lines<P>(filename: P) -> io::Result<std::iter::Enumerate<io::Lines<io::BufReader<File>>>>
-where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines().enumerate())
-}
+pub fn run(filename: &str, exchange: &str, offline: bool, format: &str, method: &str, price_url: &str, price_api_key: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut portfolio = portfolio::Portfolio::new();
 
-pub fn run(filename: &str, exchange: &str) -> Result<(), Box<dyn Error>> {
-    let mut portfolio: HashMap<String, portfolio::AssetHistory> = HashMap::new();
+    let parser = transaction::parser_for(exchange)?;
+    let oracle = price::build_oracle(offline, price_url, price_api_key);
+    let format = output::Format::from_str(format)?;
+    let method = portfolio::LotMatching::from_str(method)?;
 
-    let lines = read_lines(filename)?;
+    // a proper RFC 4180 reader so quoted fields (e.g. the notes column's
+    // embedded commas) survive, with columns mapped by header name
+    let mut reader = csv::ReaderBuilder::new()
+      .has_headers(true)
+      .flexible(true)
+      .from_path(filename)?;
 
+    let columns = transaction::column_map(reader.headers()?);
 
-    for (index, line) in lines {
-        if index == 0 {
-          //TODO: detect headers
-          continue;
-        }
+    for record in reader.records() {
+        let record = record?;
 
-        if let Ok(ip) = line {
-          let transaction = transaction::create_transaction_from_line(&ip, &exchange)?;
+        let transaction = transaction::create_transaction_from_record(&record, &columns, parser.as_ref())?;
 
-          portfolio = portfolio::add_to_portfolio(portfolio, transaction)?;
-        }
+        portfolio = portfolio::add_to_portfolio(portfolio, transaction, oracle.as_ref())?;
     }
 
-    println!("{:#?}", portfolio);
+    let gains = portfolio::compute_gains(&portfolio, method);
+
+    for diagnostic in &gains.diagnostics {
+      eprintln!("warning: {}", diagnostic);
+    }
 
-    //TODO: start going over assets and finding taxable transactions
+    let rendered = output::render(&portfolio, &gains, format);
 
     let mut output = File::create(Path::new("output.txt"))?;
 
-    output.write_all(format!("{:#?}", portfolio).as_bytes())?;
+    output.write_all(rendered.as_bytes())?;
 
     Ok(())
 }
@@ -65,7 +70,7 @@ mod lib_tests {
 
   #[test]
   fn test_coinbase_run() -> Result<(), Box<dyn Error>> {
-    match run("coinbase_test.csv", "coinbase") {
+    match run("coinbase_test.csv", "coinbase", true, "csv", "fifo", "https://api.coingecko.com/api/v3", None) {
       Ok(_) => Ok(()),
       Err(e) => Err(e)
     }
@@ -6,12 +6,27 @@ struct Opts {
     file: String,
     #[clap(short, long, default_value = "coinbase")]
     exchange: String,
+    /// Skip network price lookups; error if a required price is missing
+    #[clap(long)]
+    offline: bool,
+    /// Output format: debug, ledger, or csv
+    #[clap(long, default_value = "csv")]
+    format: String,
+    /// Lot-matching method: fifo, lifo, or hifo
+    #[clap(long, default_value = "fifo")]
+    method: String,
+    /// Base URL of the historical price endpoint
+    #[clap(long, env = "COIN_TAX_PRICE_URL", default_value = "https://api.coingecko.com/api/v3")]
+    price_url: String,
+    /// API key for the price endpoint, if it requires one
+    #[clap(long, env = "COIN_TAX_PRICE_API_KEY")]
+    price_api_key: Option<String>,
 }
 
 fn main() {
     let opts: Opts = Opts::parse();
 
-    if let Err(e) = coin_tax::run(&opts.file, &opts.exchange) {
+    if let Err(e) = coin_tax::run(&opts.file, &opts.exchange, opts.offline, &opts.format, &opts.method, &opts.price_url, opts.price_api_key.as_deref()) {
         eprintln!("Application error: {}", e);
         process::exit(1);
     }
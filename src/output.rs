@@ -0,0 +1,168 @@
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use chrono::{LocalResult, TimeZone, Utc};
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use super::portfolio::{GainsReport, Portfolio, TaxableEvent};
+use super::transaction::{Transaction, TransactionType};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OutputError {
+  #[error("Unknown output format {format:?}")]
+  UnknownFormat {
+    format: String
+  }
+}
+
+/// How `run` serializes the computed portfolio and taxable events.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+  Debug,
+  Ledger,
+  Csv
+}
+
+impl FromStr for Format {
+  type Err = OutputError;
+
+  fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    match raw {
+      "debug" => Ok(Format::Debug),
+      "ledger" => Ok(Format::Ledger),
+      "csv" => Ok(Format::Csv),
+      _ => Err(OutputError::UnknownFormat { format: String::from(raw) })
+    }
+  }
+}
+
+/// Render the portfolio and its realized gains in the requested format.
+pub(crate) fn render(portfolio: &Portfolio, gains: &GainsReport, format: Format) -> String {
+  match format {
+    Format::Debug => format!("{:#?}", portfolio),
+    Format::Csv => render_csv(gains),
+    Format::Ledger => render_ledger(portfolio, gains)
+  }
+}
+
+fn fiat(amount: Decimal) -> Decimal {
+  amount.round_dp(2)
+}
+
+fn date(timestamp: i64) -> String {
+  match Utc.timestamp_millis_opt(timestamp) {
+    LocalResult::Single(datetime) => datetime.format("%Y-%m-%d").to_string(),
+    _ => String::new()
+  }
+}
+
+/// A flat summary of every realized disposal, one row per taxable event, in the
+/// shape a capital-gains tax form expects.
+fn render_csv(gains: &GainsReport) -> String {
+  let mut out = String::from("asset,quantity,acquired,disposed,proceeds,cost_basis,gain,term\n");
+
+  for event in &gains.events {
+    let term = if event.long_term { "long" } else { "short" };
+
+    let _ = writeln!(
+      out,
+      "{},{},{},{},{},{},{},{}",
+      event.asset,
+      event.disposed_qty,
+      date(event.acquired_ts),
+      date(event.disposed_ts),
+      fiat(event.proceeds),
+      fiat(event.cost_basis),
+      fiat(event.gain),
+      term
+    );
+  }
+
+  out
+}
+
+/// Double-entry Ledger-CLI plain-text accounting, date-ordered: acquisitions
+/// come from the transaction history, disposals from the lot-matching engine so
+/// each gain is realized against the disposed asset at its cost basis. Amounts
+/// are in USD so every transaction balances to zero.
+fn render_ledger(portfolio: &Portfolio, gains: &GainsReport) -> String {
+  // (timestamp, asset) key so postings sort chronologically and deterministically
+  let mut postings: Vec<(i64, String, String)> = Vec::new();
+
+  for asset in portfolio.assets() {
+    for transaction in asset.history() {
+      if let Some(posting) = acquisition_posting(asset.name(), transaction) {
+        postings.push((transaction.timestamp, String::from(asset.name()), posting));
+      }
+    }
+  }
+
+  for event in &gains.events {
+    postings.push((event.disposed_ts, event.asset.clone(), disposal_posting(event)));
+  }
+
+  postings.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+  postings.into_iter().map(|(_, _, body)| body).collect()
+}
+
+/// The acquisition leg for `account_asset` in `transaction`, if any. Disposals
+/// (`Sell` and the outgoing leg of a `Convert`) are booked from taxable events
+/// instead, so they carry a cost basis — return `None` for those here.
+fn acquisition_posting(account_asset: &str, transaction: &Transaction) -> Option<String> {
+  let asset = transaction.asset.as_str();
+  let value = fiat(transaction.quantity * transaction.price);
+  let mut out = String::new();
+
+  match transaction.action {
+    TransactionType::Buy => {
+      let _ = writeln!(out, "{} Buy {}", date(transaction.timestamp), asset);
+      let _ = writeln!(out, "    Assets:Crypto:{}    {} USD", asset, value);
+      let _ = writeln!(out, "    Assets:Cash    {} USD", -value);
+    },
+    TransactionType::Income => {
+      let _ = writeln!(out, "{} Income {}", date(transaction.timestamp), asset);
+      let _ = writeln!(out, "    Assets:Crypto:{}    {} USD", asset, value);
+      let _ = writeln!(out, "    Income:Crypto:{}    {} USD", asset, -value);
+    },
+    TransactionType::Convert => {
+      let conversion = transaction.conversion_to.as_ref()?;
+
+      // only the received leg is an acquisition; it's funded by the proceeds of
+      // the disposal leg, which is emitted separately from the gains engine
+      if account_asset != conversion.name.as_str() {
+        return None;
+      }
+
+      let _ = writeln!(out, "{} Convert {} to {}", date(transaction.timestamp), asset, conversion.name);
+      let _ = writeln!(out, "    Assets:Crypto:{}    {} USD", conversion.name, value);
+      let _ = writeln!(out, "    Assets:Cash    {} USD", -value);
+    },
+    TransactionType::Sell => return None
+  }
+
+  out.push('\n');
+
+  Some(out)
+}
+
+/// A disposal leg: remove the asset at its cost basis, bank the proceeds, and
+/// book the difference to the short- or long-term capital-gains account.
+fn disposal_posting(event: &TaxableEvent) -> String {
+  let account = if event.long_term {
+    "Income:CapitalGains:Long"
+  } else {
+    "Income:CapitalGains:Short"
+  };
+
+  let mut out = String::new();
+
+  let _ = writeln!(out, "{} Dispose {}", date(event.disposed_ts), event.asset);
+  let _ = writeln!(out, "    Assets:Cash    {} USD", fiat(event.proceeds));
+  let _ = writeln!(out, "    Assets:Crypto:{}    {} USD", event.asset, -fiat(event.cost_basis));
+  let _ = writeln!(out, "    {}    {} USD", account, -fiat(event.gain));
+  out.push('\n');
+
+  out
+}
@@ -1,28 +1,139 @@
 use std::error::Error;
-use std::collections::{HashMap};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
 
+use rust_decimal::Decimal;
 use thiserror::Error;
 
-use super::transaction;
+use super::transaction::{self, Currency};
+use super::price::PriceOracle;
+
+// a year's worth of milliseconds, used to split short- vs long-term gains
+const ONE_YEAR_MILLIS: i64 = 365 * 24 * 60 * 60 * 1000;
+
+// a day's worth of milliseconds, the granularity price points are cached at
+const ONE_DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum PortfolioError {
   #[error("Error Accessing History for {attempted_access:?}")]
   HistoryAccessError {
     attempted_access: String
+  },
+  #[error("Unknown lot-matching method {method:?}")]
+  UnknownMethod {
+    method: String
   }
 }
 
-type Portfolio = HashMap<String, AssetHistory>;
+/// The full set of tracked assets plus a per-(asset, day) cache of fetched
+/// fair-market prices, so repeated lookups of the same point within one run
+/// query the oracle once. The cache is held in memory and not persisted between runs.
+#[derive(Debug, Default)]
+pub(crate) struct Portfolio {
+  assets: HashMap<String, AssetHistory>,
+  price_cache: HashMap<(String, i64), Decimal>,
+}
+
+impl Portfolio {
+  pub(crate) fn new() -> Self {
+    Portfolio::default()
+  }
+
+  /// The tracked assets, for output layers that walk every history.
+  pub(crate) fn assets(&self) -> std::collections::hash_map::Values<String, AssetHistory> {
+    self.assets.values()
+  }
+
+  /// Fair-market price for `asset` on the day of `timestamp`, consulting the
+  /// cache before falling back to the oracle.
+  fn spot(&mut self, oracle: &dyn PriceOracle, asset: &Currency, timestamp: i64) -> Result<Decimal, Box<dyn Error>> {
+    let key = (asset.to_string(), timestamp / ONE_DAY_MILLIS);
+
+    if let Some(price) = self.price_cache.get(&key) {
+      return Ok(*price);
+    }
+
+    let price = oracle.spot(asset, timestamp)?;
+    self.price_cache.insert(key, price);
+
+    Ok(price)
+  }
+}
+
+/// Which acquisition lot a disposal is matched against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LotMatching {
+  Fifo,
+  Lifo,
+  Hifo
+}
+
+impl FromStr for LotMatching {
+  type Err = PortfolioError;
+
+  fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    match raw {
+      "fifo" => Ok(LotMatching::Fifo),
+      "lifo" => Ok(LotMatching::Lifo),
+      "hifo" => Ok(LotMatching::Hifo),
+      _ => Err(PortfolioError::UnknownMethod { method: String::from(raw) })
+    }
+  }
+}
+
+/// An open acquisition lot awaiting disposal.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Lot {
+  quantity: Decimal,
+  cost_basis_per_unit: Decimal,
+  acquired_timestamp: i64,
+}
+
+/// A realized disposal of (part of) a lot.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TaxableEvent {
+  pub(crate) asset: String,
+  pub(crate) disposed_qty: Decimal,
+  pub(crate) proceeds: Decimal,
+  pub(crate) cost_basis: Decimal,
+  pub(crate) gain: Decimal,
+  pub(crate) acquired_ts: i64,
+  pub(crate) disposed_ts: i64,
+  pub(crate) long_term: bool,
+}
+
+/// Realized gain/loss for one asset, split by holding period.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RealizedTotals {
+  pub(crate) short_term: Decimal,
+  pub(crate) long_term: Decimal,
+}
+
+/// The result of a full lot-matching pass over a portfolio.
+#[derive(Debug, Default)]
+pub(crate) struct GainsReport {
+  pub(crate) events: Vec<TaxableEvent>,
+  pub(crate) realized: HashMap<String, RealizedTotals>,
+  pub(crate) diagnostics: Vec<String>,
+}
 
 #[derive(Debug)]
 pub(crate) struct AssetHistory {
   name: String,
   history: Vec<transaction::Transaction>,
-  quantity: f32,
+  quantity: Decimal,
 }
 
 impl AssetHistory {
+  pub(crate) fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub(crate) fn history(&self) -> &[transaction::Transaction] {
+    &self.history
+  }
+
   fn push_into_history(&mut self, new_transaction: transaction::Transaction) {
     let pos = self.history.binary_search(&new_transaction).unwrap_or_else(|e| e);
     self.history.insert(pos, new_transaction);
@@ -42,7 +153,7 @@ impl AssetHistory {
       transaction::TransactionType::Convert => {
         let conversion = new_transaction.conversion_to.clone().unwrap();
         
-        if self.name == conversion.name {
+        if self.name == conversion.name.as_str() {
           self.quantity += conversion.quantity;
         }
         else {
@@ -55,72 +166,238 @@ impl AssetHistory {
   }
 }
 
-fn add_new_asset_to_portfolio(mut portfolio: Portfolio, asset: &str) -> Portfolio {
-  if !portfolio.contains_key(asset) {
-    let asset_history = AssetHistory {
-        name: String::from(asset),
-        history: Vec::new(),
-        quantity: 0.0
-      };
+fn add_new_asset_to_portfolio(portfolio: &mut Portfolio, asset: &str) {
+  portfolio.assets.entry(String::from(asset)).or_insert_with(|| AssetHistory {
+    name: String::from(asset),
+    history: Vec::new(),
+    quantity: Decimal::ZERO
+  });
+}
 
-    portfolio.insert(String::from(asset), asset_history);
+/// Book income and the receiving leg of a convert at fair-market value when the
+/// export didn't carry a per-unit spot price, querying the oracle at the
+/// transaction timestamp. `price` is the per-unit spot (zero when the
+/// "Spot Price at Transaction" cell was blank), so gate the fill on that.
+fn fill_missing_prices(portfolio: &mut Portfolio, transaction: &mut transaction::Transaction, oracle: &dyn PriceOracle) -> Result<(), Box<dyn Error>> {
+  match transaction.action {
+    transaction::TransactionType::Income if transaction.price.is_zero() => {
+      transaction.price = portfolio.spot(oracle, &transaction.asset, transaction.timestamp)?;
+    },
+    transaction::TransactionType::Convert if transaction.price.is_zero() => {
+      if let Some(conversion) = transaction.conversion_to.clone() {
+        // value the trade from the received asset's fair-market price
+        let received_spot = portfolio.spot(oracle, &conversion.name, transaction.timestamp)?;
+
+        if !transaction.quantity.is_zero() {
+          transaction.price = received_spot * conversion.quantity / transaction.quantity;
+        }
+      }
+    },
+    _ => {}
   }
-  
-  portfolio
+
+  Ok(())
 }
 
+pub(crate) fn add_to_portfolio(mut portfolio: Portfolio, mut transaction: transaction::Transaction, oracle: &dyn PriceOracle) -> Result<Portfolio, Box<dyn Error>> {
+  fill_missing_prices(&mut portfolio, &mut transaction, oracle)?;
 
-pub(crate) fn add_to_portfolio(mut portfolio: Portfolio, transaction: transaction::Transaction) -> Result<Portfolio, Box<dyn Error>> {
-  portfolio = add_new_asset_to_portfolio(portfolio, &transaction.asset);
+  add_new_asset_to_portfolio(&mut portfolio, transaction.asset.as_str());
 
   // handle update to converted currency
   if transaction.action == transaction::TransactionType::Convert {
     let conversion = &transaction.conversion_to.clone().unwrap();
 
-    portfolio = add_new_asset_to_portfolio(portfolio, &conversion.name);
+    add_new_asset_to_portfolio(&mut portfolio, conversion.name.as_str());
 
-    let coverted_to_asset = portfolio.get_mut(&conversion.name).ok_or(PortfolioError::HistoryAccessError{attempted_access: String::from(&conversion.name)})?;
+    let coverted_to_asset = portfolio.assets.get_mut(conversion.name.as_str()).ok_or(PortfolioError::HistoryAccessError{attempted_access: conversion.name.to_string()})?;
 
     coverted_to_asset.add_transaction_to_asset(transaction.clone());
   }
 
-  let asset_history = portfolio.get_mut(&transaction.asset).ok_or(PortfolioError::HistoryAccessError{attempted_access:String::from(&transaction.asset)})?;
+  let asset_history = portfolio.assets.get_mut(transaction.asset.as_str()).ok_or(PortfolioError::HistoryAccessError{attempted_access: transaction.asset.to_string()})?;
 
   asset_history.add_transaction_to_asset(transaction);
 
   Ok(portfolio)
 }
 
+/// Pull the next lot to dispose against, honoring the selected matching method.
+fn take_lot(lots: &mut VecDeque<Lot>, method: LotMatching) -> Option<Lot> {
+  match method {
+    LotMatching::Fifo => lots.pop_front(),
+    LotMatching::Lifo => lots.pop_back(),
+    LotMatching::Hifo => {
+      let mut highest = None;
+      for (index, lot) in lots.iter().enumerate() {
+        match highest {
+          Some((_, basis)) if lot.cost_basis_per_unit <= basis => {},
+          _ => highest = Some((index, lot.cost_basis_per_unit)),
+        }
+      }
+      highest.and_then(|(index, _)| lots.remove(index))
+    }
+  }
+}
+
+/// Match `disposed_qty` (with total `proceeds`) against open `lots`, emitting one
+/// `TaxableEvent` per consumed lot fragment and accumulating realized totals.
+fn dispose(
+  asset: &str,
+  mut disposed_qty: Decimal,
+  proceeds: Decimal,
+  disposed_ts: i64,
+  lots: &mut VecDeque<Lot>,
+  method: LotMatching,
+  report: &mut GainsReport,
+) {
+  let total_qty = disposed_qty;
+
+  while disposed_qty > Decimal::ZERO {
+    let mut lot = match take_lot(lots, method) {
+      Some(lot) => lot,
+      None => {
+        report.diagnostics.push(format!(
+          "{}: disposal of {} at {} has no prior acquisition lot (missing transfer in?)",
+          asset, disposed_qty, disposed_ts
+        ));
+        break;
+      }
+    };
+
+    let consumed = disposed_qty.min(lot.quantity);
+    // proceeds are allocated to this fragment by its share of the disposal
+    let fragment_proceeds = proceeds * (consumed / total_qty);
+    let cost_basis = consumed * lot.cost_basis_per_unit;
+    let gain = fragment_proceeds - cost_basis;
+    let long_term = disposed_ts - lot.acquired_timestamp >= ONE_YEAR_MILLIS;
+
+    let totals = report.realized.entry(String::from(asset)).or_default();
+    if long_term {
+      totals.long_term += gain;
+    } else {
+      totals.short_term += gain;
+    }
+
+    report.events.push(TaxableEvent {
+      asset: String::from(asset),
+      disposed_qty: consumed,
+      proceeds: fragment_proceeds,
+      cost_basis,
+      gain,
+      acquired_ts: lot.acquired_timestamp,
+      disposed_ts,
+      long_term,
+    });
+
+    // split the lot if it was only partially consumed
+    if lot.quantity > consumed {
+      lot.quantity -= consumed;
+      match method {
+        LotMatching::Lifo => lots.push_back(lot),
+        _ => lots.push_front(lot),
+      }
+    }
+
+    disposed_qty -= consumed;
+  }
+}
+
+/// Walk every asset's time-sorted history, matching disposals against acquisitions
+/// to produce realized gain/loss records and per-asset short/long-term totals.
+pub(crate) fn compute_gains(portfolio: &Portfolio, method: LotMatching) -> GainsReport {
+  let mut report = GainsReport::default();
+
+  for asset in portfolio.assets.values() {
+    let mut lots: VecDeque<Lot> = VecDeque::new();
+
+    for transaction in &asset.history {
+      match transaction.action {
+        transaction::TransactionType::Buy => {
+          lots.push_back(Lot {
+            quantity: transaction.quantity,
+            cost_basis_per_unit: transaction.price,
+            acquired_timestamp: transaction.timestamp,
+          });
+        },
+        transaction::TransactionType::Income => {
+          // income lots are booked at fair-market value on receipt
+          lots.push_back(Lot {
+            quantity: transaction.quantity,
+            cost_basis_per_unit: transaction.price,
+            acquired_timestamp: transaction.timestamp,
+          });
+        },
+        transaction::TransactionType::Sell => {
+          let proceeds = transaction.quantity * transaction.price;
+          dispose(&asset.name, transaction.quantity, proceeds, transaction.timestamp, &mut lots, method, &mut report);
+        },
+        transaction::TransactionType::Convert => {
+          let conversion = transaction.conversion_to.clone().unwrap();
+          // the fiat value of the trade, shared by both legs
+          let fiat_value = transaction.quantity * transaction.price;
+
+          if asset.name == conversion.name {
+            // receiving leg: a fresh acquisition at the fiat value of what was given up
+            lots.push_back(Lot {
+              quantity: conversion.quantity,
+              cost_basis_per_unit: fiat_value / conversion.quantity,
+              acquired_timestamp: transaction.timestamp,
+            });
+          } else {
+            // outgoing leg: a disposal with proceeds equal to the fiat value received
+            dispose(&asset.name, transaction.quantity, fiat_value, transaction.timestamp, &mut lots, method, &mut report);
+          }
+        }
+      }
+    }
+  }
+
+  // events are produced per-asset in HashMap order; a tax-form CSV and a Ledger
+  // file must be chronological, so order by disposal date (tie-break by asset)
+  report.events.sort_by(|a, b| {
+    a.disposed_ts.cmp(&b.disposed_ts).then_with(|| a.asset.cmp(&b.asset))
+  });
+
+  report
+}
+
 #[cfg(test)]
 mod lib_tests {
-  use transaction::{Transaction, TransactionType};
+  use std::str::FromStr;
+
+  use transaction::{Transaction, TransactionType, Currency};
   use super::*;
+  use super::super::price::OfflineOracle;
+  use crate::transaction::{column_map, create_transaction_from_record, CoinbaseParser, ExchangeParser};
+  use csv::StringRecord;
+  use rust_decimal_macros::dec;
 
   #[test]
   fn test_add_new_asset_to_portfolio() {
-    let mut test_portfolio: HashMap<String, AssetHistory> = HashMap::new();
+    let mut test_portfolio = Portfolio::new();
 
     let asset_to_add = "BTC";
 
-    test_portfolio = add_new_asset_to_portfolio(test_portfolio, asset_to_add);
+    add_new_asset_to_portfolio(&mut test_portfolio, asset_to_add);
 
-    assert!(test_portfolio.contains_key(asset_to_add))
+    assert!(test_portfolio.assets.contains_key(asset_to_add))
   }
 
   #[test]
   fn test_asset_history_push_into_history() {
     let mut test_asset = AssetHistory {
       name: String::from("BTC"),
-      quantity: 0.0,
+      quantity: Decimal::ZERO,
       history: Vec::new()
     };
 
     let test_transaction = Transaction {
       timestamp: 123456789,
-      asset: String::from("BTC"),
+      asset: Currency::from_str("BTC").unwrap(),
       action: TransactionType::Sell,
-      price: 10.0,
-      quantity: 0.123,
+      price: dec!(10.0),
+      quantity: dec!(0.123),
       conversion_to: None
     };
 
@@ -133,22 +410,92 @@ mod lib_tests {
   fn test_add_transaction_to_asset() {
     let mut test_asset = AssetHistory {
       name: String::from("BTC"),
-      quantity: 0.0,
+      quantity: Decimal::ZERO,
       history: Vec::new()
     };
 
     let test_transaction = Transaction {
       timestamp: 123456789,
-      asset: String::from("BTC"),
+      asset: Currency::from_str("BTC").unwrap(),
       action: TransactionType::Sell,
-      price: 10.0,
-      quantity: 0.123,
+      price: dec!(10.0),
+      quantity: dec!(0.123),
       conversion_to: None
     };
 
     test_asset.add_transaction_to_asset(test_transaction);
 
-    assert_eq!(test_asset.quantity, -0.123);
+    assert_eq!(test_asset.quantity, dec!(-0.123));
     assert_eq!(test_asset.history.len(), 1);
   }
+
+  #[test]
+  fn test_compute_gains_fifo() {
+    let mut portfolio = Portfolio::new();
+    let oracle = OfflineOracle;
+
+    let buy = Transaction {
+      timestamp: 0,
+      asset: Currency::from_str("BTC").unwrap(),
+      action: TransactionType::Buy,
+      price: dec!(100.0),
+      quantity: dec!(2.0),
+      conversion_to: None
+    };
+
+    // a year and change later, sell one unit at double the price
+    let sell = Transaction {
+      timestamp: ONE_YEAR_MILLIS + 1,
+      asset: Currency::from_str("BTC").unwrap(),
+      action: TransactionType::Sell,
+      price: dec!(200.0),
+      quantity: dec!(1.0),
+      conversion_to: None
+    };
+
+    portfolio = add_to_portfolio(portfolio, buy, &oracle).unwrap();
+    portfolio = add_to_portfolio(portfolio, sell, &oracle).unwrap();
+
+    let report = compute_gains(&portfolio, LotMatching::Fifo);
+
+    assert_eq!(report.events.len(), 1);
+    assert_eq!(report.events[0].gain, dec!(100.0));
+    assert!(report.events[0].long_term);
+    assert_eq!(report.realized["BTC"].long_term, dec!(100.0));
+    assert!(report.diagnostics.is_empty());
+  }
+
+  #[test]
+  fn test_compute_gains_from_coinbase_records() {
+    // drive realistic Coinbase rows through the CSV -> engine boundary so the
+    // per-unit `price` semantics (Spot Price at Transaction, not Total) are
+    // exercised end to end, not just hand-built synthetic transactions
+    let headers: StringRecord = CoinbaseParser.column_layout().iter().copied().collect();
+    let columns = column_map(&headers);
+
+    let rows = vec![
+      // Buy $10 of BTC at a $10,000 spot; the whole-transaction Total is also $10
+      ["2019-01-01T00:00:00Z", "Buy", "BTC", "0.001", "10000.00", "10.00", "10.00", "0.00", "Bought 0.001 BTC for $10.00 USD"],
+      // a year+ later, sell the same 0.001 BTC at a $20,000 spot for $20
+      ["2020-06-01T00:00:00Z", "Sell", "BTC", "0.001", "20000.00", "20.00", "20.00", "0.00", "Sold 0.001 BTC for $20.00 USD"],
+    ];
+
+    let mut portfolio = Portfolio::new();
+    let oracle = OfflineOracle;
+
+    for row in rows {
+      let record: StringRecord = row.iter().copied().collect();
+      let transaction = create_transaction_from_record(&record, &columns, &CoinbaseParser).unwrap();
+      portfolio = add_to_portfolio(portfolio, transaction, &oracle).unwrap();
+    }
+
+    let report = compute_gains(&portfolio, LotMatching::Fifo);
+
+    assert_eq!(report.events.len(), 1);
+    // proceeds $20 - cost basis $10 = $10, not the $0.0001 a Total-as-per-unit bug would give
+    assert_eq!(report.events[0].proceeds, dec!(20.000));
+    assert_eq!(report.events[0].cost_basis, dec!(10.000));
+    assert_eq!(report.events[0].gain, dec!(10.000));
+    assert!(report.diagnostics.is_empty());
+  }
 }
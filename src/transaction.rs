@@ -1,8 +1,12 @@
 use std::error::Error;
 use std::str::FromStr;
 use std::cmp::{PartialEq, PartialOrd, Ord, Ordering};
+use std::collections::HashMap;
+use std::fmt;
 
 use chrono::{DateTime};
+use csv::StringRecord;
+use rust_decimal::Decimal;
 use thiserror::Error;
 
 #[derive(Debug, Clone)]
@@ -14,19 +18,50 @@ pub(crate) enum TransactionType {
   Convert
 }
 
+/// A ticker symbol (e.g. `BTC`, `ALGO`), normalized so that lookups compare equal
+/// regardless of the casing an exchange happens to export.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Currency(String);
+
+impl Currency {
+  pub(crate) fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+impl FromStr for Currency {
+  type Err = TransactionError;
+
+  fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    let ticker = raw.trim().to_uppercase();
+
+    if ticker.is_empty() {
+      return Err(TransactionError::UnknownCurrency);
+    }
+
+    Ok(Currency(ticker))
+  }
+}
+
+impl fmt::Display for Currency {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct CoinConversion {
-  pub (crate) name: String,
-  pub (crate) quantity: f32
+  pub (crate) name: Currency,
+  pub (crate) quantity: Decimal
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Transaction {
   pub(crate) timestamp: i64,
   pub(crate) action: TransactionType,
-  pub(crate) asset: String,
-  pub(crate) quantity: f32,
-  pub(crate) price: f32,
+  pub(crate) asset: Currency,
+  pub(crate) quantity: Decimal,
+  pub(crate) price: Decimal,
   pub(crate) conversion_to: Option<CoinConversion>
 }
 
@@ -48,12 +83,18 @@ impl Ord for Transaction {
 pub enum TransactionError {
   #[error("Unknown Action")]
   UnknownAction,
+  #[error("Unknown Currency")]
+  UnknownCurrency,
+  #[error("Missing expected column {column:?}")]
+  MissingColumn {
+    column: String
+  },
   #[error("Exchange not yet supported!")]
   UnsupportedExchange,
   #[error(transparent)]
   TimeParseError(#[from] chrono::ParseError),
   #[error(transparent)]
-  FloatParseError(#[from] std::num::ParseFloatError)
+  DecimalParseError(#[from] rust_decimal::Error)
 }
 
 fn get_coinbase_action(raw_action: &str) -> Result<TransactionType, TransactionError> {
@@ -67,77 +108,140 @@ fn get_coinbase_action(raw_action: &str) -> Result<TransactionType, TransactionE
   }
 }
 
-fn get_action_by_exchange(line_data: &Vec<&str>, exchange: &str) -> Result<TransactionType, TransactionError> {
-  match exchange {
-    "coinbase" => Ok(get_coinbase_action(line_data[1])?),
-    _ => Err(TransactionError::UnsupportedExchange)
-  }
+/// A single parsed CSV record together with the header-driven column mapping,
+/// so extractors can reference fields by name instead of a fixed position.
+pub(crate) struct Row<'a> {
+  record: &'a StringRecord,
+  columns: &'a HashMap<String, usize>,
 }
 
-fn get_timestamp_by_exchange(line_data: &Vec<&str>, exchange: &str) -> Result<i64, TransactionError> {
-  match exchange {
-    "coinbase" => Ok(DateTime::parse_from_rfc3339(line_data[0])?.timestamp_millis()),
-    _ => Err(TransactionError::UnsupportedExchange)
+impl<'a> Row<'a> {
+  pub(crate) fn new(record: &'a StringRecord, columns: &'a HashMap<String, usize>) -> Self {
+    Row { record, columns }
   }
-}
 
-fn get_asset_by_exchange(line_data: &Vec<&str>, exchange: &str) -> Result<String, TransactionError> {
-  match exchange {
-    "coinbase" => Ok(String::from(line_data[2])),
-    _ => Err(TransactionError::UnsupportedExchange)
+  fn get(&self, column: &str) -> Result<&str, TransactionError> {
+    self.columns
+      .get(column)
+      .and_then(|index| self.record.get(*index))
+      .ok_or_else(|| TransactionError::MissingColumn { column: String::from(column) })
   }
 }
 
-fn get_quantity_by_exchange(line_data: &Vec<&str>, exchange: &str) -> Result<f32, TransactionError> {
-  match exchange {
-    "coinbase" => Ok(f32::from_str(line_data[3])?),
-    _ => Err(TransactionError::UnsupportedExchange)
-  }
+/// Build a name -> index lookup from a CSV header row, so downstream column order
+/// changes across exchange export versions are non-breaking.
+pub(crate) fn column_map(headers: &StringRecord) -> HashMap<String, usize> {
+  headers
+    .iter()
+    .enumerate()
+    .map(|(index, name)| (String::from(name.trim()), index))
+    .collect()
 }
 
-fn get_price_by_exchange(line_data: &Vec<&str>, exchange: &str) -> Result<f32, TransactionError> {
-    match exchange {
-    "coinbase" => Ok(f32::from_str(line_data[6])?),
-    _ => Err(TransactionError::UnsupportedExchange)
-  }
+/// One extractor per field, implemented once per supported exchange. Adding
+/// Binance/Kraken/etc. means a new implementor, not edits to seven match arms.
+pub(crate) trait ExchangeParser {
+  fn parse_action(&self, row: &Row) -> Result<TransactionType, TransactionError>;
+  fn parse_timestamp(&self, row: &Row) -> Result<i64, TransactionError>;
+  fn parse_asset(&self, row: &Row) -> Result<Currency, TransactionError>;
+  fn parse_quantity(&self, row: &Row) -> Result<Decimal, TransactionError>;
+  fn parse_price(&self, row: &Row) -> Result<Decimal, TransactionError>;
+  fn parse_conversion(&self, row: &Row) -> Result<Option<CoinConversion>, TransactionError>;
+
+  /// The header signature this parser expects, in column order.
+  fn column_layout(&self) -> &'static [&'static str];
 }
 
-fn get_conversion_to_by_exchange(line_data: &Vec<&str>, exchange: &str) -> Result<Option<CoinConversion>, TransactionError> {
-    match exchange {
-    "coinbase" => {
-      let note_data: Vec<&str> = line_data.last().unwrap().split(" ").collect::<Vec<&str>>();
+pub(crate) struct CoinbaseParser;
 
-      Ok(Some(CoinConversion {
-        name: String::from(*note_data.last().unwrap()).replace('"', ""),
-        quantity: f32::from_str(note_data[note_data.len() - 2])?
-      }))
-    },
-    _ => Err(TransactionError::UnsupportedExchange)
+impl ExchangeParser for CoinbaseParser {
+  fn parse_action(&self, row: &Row) -> Result<TransactionType, TransactionError> {
+    get_coinbase_action(row.get("Transaction Type")?)
   }
-}
 
+  fn parse_timestamp(&self, row: &Row) -> Result<i64, TransactionError> {
+    Ok(DateTime::parse_from_rfc3339(row.get("Timestamp")?)?.timestamp_millis())
+  }
 
+  fn parse_asset(&self, row: &Row) -> Result<Currency, TransactionError> {
+    Currency::from_str(row.get("Asset")?)
+  }
 
-fn split_string(string: &str, delimeter: Option<char>) -> Vec<&str> {
-  string.split(delimeter.unwrap_or(',')).collect()
-}
+  fn parse_quantity(&self, row: &Row) -> Result<Decimal, TransactionError> {
+    Ok(Decimal::from_str(row.get("Quantity Transacted")?)?)
+  }
 
-fn split_csv_line(line: &str) -> Vec<&str> {
-  split_string(&line, None)
+  fn parse_price(&self, row: &Row) -> Result<Decimal, TransactionError> {
+    // the per-unit spot price, not the whole-transaction "Total" — the gains
+    // engine and the ledger/csv output both consume `price` per-unit. Income and
+    // Convert rows may leave the cell blank; yield zero so the price oracle fills
+    // the fair-market value in `portfolio::fill_missing_prices`.
+    let raw = row.get("Spot Price at Transaction")?.trim();
+
+    if raw.is_empty() {
+      return Ok(Decimal::ZERO);
+    }
+
+    Ok(Decimal::from_str(raw)?)
+  }
+
+  fn parse_conversion(&self, row: &Row) -> Result<Option<CoinConversion>, TransactionError> {
+    // the notes column is quoted and holds embedded commas, e.g.
+    // `Converted 1,641.4065951 XLM to 774.762752 ALGO` — the CSV reader has
+    // already handled the quoting, so a space split yields clean tokens.
+    let note_data: Vec<&str> = row.get("Notes")?.split(' ').collect::<Vec<&str>>();
+
+    // a convert note ends in `... <quantity> <ticker>`; a short/empty note can't
+    // be a conversion, so surface a missing column rather than underflowing
+    if note_data.len() < 2 {
+      return Err(TransactionError::MissingColumn { column: String::from("Notes") });
+    }
+
+    Ok(Some(CoinConversion {
+      name: Currency::from_str(note_data[note_data.len() - 1])?,
+      quantity: Decimal::from_str(note_data[note_data.len() - 2])?
+    }))
+  }
+
+  fn column_layout(&self) -> &'static [&'static str] {
+    &[
+      "Timestamp",
+      "Transaction Type",
+      "Asset",
+      "Quantity Transacted",
+      "Spot Price at Transaction",
+      "Subtotal",
+      "Total",
+      "Fees",
+      "Notes"
+    ]
+  }
 }
 
-pub(crate) fn create_transaction_from_line(line: &str, exchange: &str) -> Result<Transaction, Box<dyn Error>> {
-  let split_line = split_csv_line(line);
+/// Select the parser for an exchange once, up front, from the `--exchange` flag.
+pub(crate) fn parser_for(exchange: &str) -> Result<Box<dyn ExchangeParser>, TransactionError> {
+  match exchange {
+    "coinbase" => Ok(Box::new(CoinbaseParser)),
+    _ => Err(TransactionError::UnsupportedExchange)
+  }
+}
 
-  let timestamp = get_timestamp_by_exchange(&split_line, &exchange)?;
-  let action = get_action_by_exchange(&split_line, &exchange)?;
-  let asset = get_asset_by_exchange(&split_line, &exchange)?;
-  let quantity = get_quantity_by_exchange(&split_line, &exchange)?;
-  let price = get_price_by_exchange(&split_line, &exchange)?;
+pub(crate) fn create_transaction_from_record(
+  record: &StringRecord,
+  columns: &HashMap<String, usize>,
+  parser: &dyn ExchangeParser,
+) -> Result<Transaction, Box<dyn Error>> {
+  let row = Row::new(record, columns);
+
+  let timestamp = parser.parse_timestamp(&row)?;
+  let action = parser.parse_action(&row)?;
+  let asset = parser.parse_asset(&row)?;
+  let quantity = parser.parse_quantity(&row)?;
+  let price = parser.parse_price(&row)?;
   let mut conversion_to = None;
 
   if action == TransactionType::Convert {
-    conversion_to = get_conversion_to_by_exchange(&split_line, &exchange)?;
+    conversion_to = parser.parse_conversion(&row)?;
   }
 
   Ok(Transaction {
@@ -153,152 +257,127 @@ pub(crate) fn create_transaction_from_line(line: &str, exchange: &str) -> Result
 #[cfg(test)]
 mod tests {
   use super::*;
+  use rust_decimal_macros::dec;
+
+  // the coinbase header signature, for mapping a data row to columns by name
+  fn coinbase_columns() -> HashMap<String, usize> {
+    let headers: StringRecord = CoinbaseParser.column_layout().iter().copied().collect();
+    column_map(&headers)
+  }
 
-  fn do_vecs_match<T: PartialEq>(a: &Vec<T>, b: &Vec<T>) -> bool {
-    let matching = a.iter().zip(b.iter()).filter(|&(a, b)| a == b).count();
-    matching == a.len() && matching == b.len()
+  fn coinbase_record(data: &[&str]) -> StringRecord {
+    data.iter().copied().collect()
   }
 
   #[test]
-  fn test_get_action_by_exchange() -> Result<(), Box<dyn Error>> {
-    let test_buy_data = vec!["2018-01-23T03:40:11Z","Buy","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let test_sell_data = vec!["2018-01-23T03:40:11Z","Sell","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let test_income_data = vec!["2018-01-23T03:40:11Z","Rewards Income","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let test_earn_data = vec!["2018-01-23T03:40:11Z","Coinbase Earn","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let test_convert_data = vec!["2018-01-23T03:40:11Z","Convert","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let test_unknown_data = vec!["2018-01-23T03:40:11Z","Unknown","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-
-    let my_buy_action = get_action_by_exchange(&test_buy_data, "coinbase")?;
-    let my_sell_action = get_action_by_exchange(&test_sell_data, "coinbase")?;
-    let my_income_action = get_action_by_exchange(&test_income_data, "coinbase")?;
-    let my_earn_action = get_action_by_exchange(&test_earn_data, "coinbase")?;
-    let my_convert_action = get_action_by_exchange(&test_convert_data, "coinbase")?;
-    let my_unknown_action = get_action_by_exchange(&test_unknown_data, "coinbase").unwrap_err();
-    let my_unsupported_exchange = get_action_by_exchange(&test_buy_data, "coinfake").unwrap_err();
-
-    assert_eq!(my_buy_action, TransactionType::Buy);
-    assert_eq!(my_sell_action, TransactionType::Sell);
-    assert_eq!(my_income_action, TransactionType::Income);
-    assert_eq!(my_earn_action, TransactionType::Income);
-    assert_eq!(my_convert_action, TransactionType::Convert);
-    assert_eq!(my_unknown_action, TransactionError::UnknownAction);
+  fn test_parser_for_unsupported_exchange() {
+    let my_unsupported_exchange = parser_for("coinfake").unwrap_err();
+
     assert_eq!(my_unsupported_exchange, TransactionError::UnsupportedExchange);
+  }
+
+  #[test]
+  fn test_coinbase_parse_action() -> Result<(), Box<dyn Error>> {
+    let parser = CoinbaseParser;
+    let columns = coinbase_columns();
+
+    let actions = vec![
+      ("Buy", TransactionType::Buy),
+      ("Sell", TransactionType::Sell),
+      ("Rewards Income", TransactionType::Income),
+      ("Coinbase Earn", TransactionType::Income),
+      ("Convert", TransactionType::Convert),
+    ];
+
+    for (raw_action, expected) in actions {
+      let record = coinbase_record(&["2018-01-23T03:40:11Z", raw_action, "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "Bought 0.000919 BTC for $10.00 USD"]);
+      assert_eq!(parser.parse_action(&Row::new(&record, &columns))?, expected);
+    }
+
+    let unknown = coinbase_record(&["2018-01-23T03:40:11Z", "Unknown", "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "note"]);
+    assert_eq!(parser.parse_action(&Row::new(&unknown, &columns)).unwrap_err(), TransactionError::UnknownAction);
 
     Ok(())
   }
 
   #[test]
-  fn test_get_timestamp_by_exchange() {
-    let test_data = vec!["2018-01-23T03:40:11Z","Buy","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
+  fn test_coinbase_parse_timestamp() {
+    let columns = coinbase_columns();
+    let record = coinbase_record(&["2018-01-23T03:40:11Z", "Buy", "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "note"]);
 
     let expected_result = 1516678811000;
 
-    let my_timestamp = get_timestamp_by_exchange(&test_data, "coinbase").unwrap();
-    let my_unsupported_exchange = get_timestamp_by_exchange(&test_data, "coinfake").unwrap_err();
-
-    assert_eq!(my_timestamp, expected_result);
-    assert_eq!(my_unsupported_exchange, TransactionError::UnsupportedExchange);
+    assert_eq!(CoinbaseParser.parse_timestamp(&Row::new(&record, &columns)).unwrap(), expected_result);
   }
 
   #[test]
-  fn test_get_asset_by_exchange() {
-    let test_data = vec!["2018-01-23T03:40:11Z","Buy","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let expected_result = "BTC";
-
-    let my_asset = get_asset_by_exchange(&test_data, "coinbase").unwrap();
-    let my_unsupported_exchange = get_asset_by_exchange(&test_data, "coinfake").unwrap_err();
+  fn test_coinbase_parse_asset() {
+    let columns = coinbase_columns();
+    let record = coinbase_record(&["2018-01-23T03:40:11Z", "Buy", "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "note"]);
 
-    assert_eq!(my_asset, expected_result);
-    assert_eq!(my_unsupported_exchange, TransactionError::UnsupportedExchange);
+    assert_eq!(CoinbaseParser.parse_asset(&Row::new(&record, &columns)).unwrap().as_str(), "BTC");
   }
 
   #[test]
-  fn test_get_quantity_by_exchange() {
-    let test_data = vec!["2018-01-23T03:40:11Z","Buy","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let expected_result = 0.000919;
-
-    let my_quantity = get_quantity_by_exchange(&test_data, "coinbase").unwrap();
-    let my_unsupported_exchange = get_quantity_by_exchange(&test_data, "coinfake").unwrap_err();
+  fn test_coinbase_parse_quantity() {
+    let columns = coinbase_columns();
+    let record = coinbase_record(&["2018-01-23T03:40:11Z", "Buy", "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "note"]);
 
-    assert_eq!(my_quantity, expected_result);
-    assert_eq!(my_unsupported_exchange, TransactionError::UnsupportedExchange);
+    assert_eq!(CoinbaseParser.parse_quantity(&Row::new(&record, &columns)).unwrap(), dec!(0.000919));
   }
 
   #[test]
-  fn test_get_price_by_exchange() {
-    let test_data = vec!["2018-01-23T03:40:11Z","Buy","BTC","0.000919","10881.58","10.00","10.00","0.00","Bought 0.000919 BTC for $10.00 USD"];
-    let expected_result = 10.00;
-
-    let my_price = get_price_by_exchange(&test_data, "coinbase").unwrap();
-    let my_unsupported_exchange = get_price_by_exchange(&test_data, "coinfake").unwrap_err();
+  fn test_coinbase_parse_price() {
+    let columns = coinbase_columns();
+    let record = coinbase_record(&["2018-01-23T03:40:11Z", "Buy", "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "note"]);
 
-    assert_eq!(my_price, expected_result);
-    assert_eq!(my_unsupported_exchange, TransactionError::UnsupportedExchange);
+    assert_eq!(CoinbaseParser.parse_price(&Row::new(&record, &columns)).unwrap(), dec!(10881.58));
   }
 
   #[test]
-  fn test_get_conversion_to_by_exchange() {
-    let test_data = vec!["2021-01-31T05:20:47Z","Convert","XLM","1641.4065951","0.310000","505.34","515.01","9.67","Converted 1,641.4065951 XLM to 774.762752 ALGO"];
+  fn test_coinbase_parse_conversion() {
+    let columns = coinbase_columns();
+    // the notes field carries an embedded comma; the CSV reader keeps it intact
+    let record = coinbase_record(&["2021-01-31T05:20:47Z", "Convert", "XLM", "1641.4065951", "0.310000", "505.34", "515.01", "9.67", "Converted 1,641.4065951 XLM to 774.762752 ALGO"]);
     let expected_result = Some(CoinConversion {
-      name: String::from("ALGO"),
-      quantity: 774.762752
+      name: Currency::from_str("ALGO").unwrap(),
+      quantity: dec!(774.762752)
     });
 
-    let my_conversion_to = get_conversion_to_by_exchange(&test_data, "coinbase").unwrap();
-    let my_unsupported_exchange = get_price_by_exchange(&test_data, "coinfake").unwrap_err();
-
-    assert_eq!(my_conversion_to, expected_result);
-    assert_eq!(my_unsupported_exchange, TransactionError::UnsupportedExchange);
-  }
-
-  #[test]
-  fn test_split_string() {
-      let expected_result = vec!["this", "is", "a", "test", "string"];
-      let test_string = "this,is,a,test,string";
-      let pipe_separated = "this|is|a|test|string";
-
-      let my_split_string = split_string(&test_string, None);
-      let pipe_split_string = split_string(&pipe_separated, Some('|'));
-
-      assert_eq!(my_split_string.len(), expected_result.len());
-      assert!(do_vecs_match(&my_split_string, &expected_result));
-      assert_eq!(pipe_split_string.len(), expected_result.len());
-      assert!(do_vecs_match(&pipe_split_string, &expected_result));
+    assert_eq!(CoinbaseParser.parse_conversion(&Row::new(&record, &columns)).unwrap(), expected_result);
   }
 
   #[test]
-  fn test_split_csv_line() {
-    let expected_result = vec!["this", "is", "a", "test", "string"];
-    let test_string = "this,is,a,test,string";
-    let pipe_separated = "this|is|a|test|string";
-
-    let my_split_string = split_csv_line(&test_string);
-    let pipe_split_string = split_csv_line(&pipe_separated);
-
-    assert_eq!(my_split_string.len(), expected_result.len());
-    assert!(do_vecs_match(&my_split_string, &expected_result));
-    assert_ne!(pipe_split_string.len(), expected_result.len());
-    assert!(!do_vecs_match(&pipe_split_string, &expected_result));
+  fn test_coinbase_parse_conversion_short_note() {
+    let columns = coinbase_columns();
+    // a Convert row whose Notes cell is too short to carry `<quantity> <ticker>`
+    let record = coinbase_record(&["2021-01-31T05:20:47Z", "Convert", "XLM", "1641.4065951", "0.310000", "505.34", "515.01", "9.67", ""]);
+
+    assert_eq!(
+      CoinbaseParser.parse_conversion(&Row::new(&record, &columns)).unwrap_err(),
+      TransactionError::MissingColumn { column: String::from("Notes") }
+    );
   }
 
   #[test]
-  fn test_create_transaction_from_line() {
-    let test_string = "2018-01-23T03:40:11Z,Buy,BTC,0.000919,10881.58,10.00,10.00,0.00,Bought 0.000919 BTC for $10.00 USD";
+  fn test_create_transaction_from_record() {
+    let columns = coinbase_columns();
+    let record = coinbase_record(&["2018-01-23T03:40:11Z", "Buy", "BTC", "0.000919", "10881.58", "10.00", "10.00", "0.00", "Bought 0.000919 BTC for $10.00 USD"]);
     let expected_result = Transaction {
       timestamp: 1516678811000,
       action: TransactionType::Buy,
-      asset: String::from("BTC"),
-      quantity: 0.000919,
-      price: 10.00,
+      asset: Currency::from_str("BTC").unwrap(),
+      quantity: dec!(0.000919),
+      price: dec!(10881.58),
       conversion_to: None
     };
 
-    let transaction = create_transaction_from_line(&test_string, "coinbase").unwrap();
+    let transaction = create_transaction_from_record(&record, &columns, &CoinbaseParser).unwrap();
 
     assert_eq!(transaction.timestamp, expected_result.timestamp);
     assert_eq!(transaction.action, expected_result.action);
     assert_eq!(transaction.asset, expected_result.asset);
-    assert_eq!(transaction.timestamp, expected_result.timestamp);
-    assert_eq!(transaction.timestamp, expected_result.timestamp);
+    assert_eq!(transaction.quantity, expected_result.quantity);
+    assert_eq!(transaction.price, expected_result.price);
   }
 }
\ No newline at end of file